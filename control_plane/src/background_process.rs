@@ -12,12 +12,14 @@
 //!
 //! See [`lock_file`] module for more info.
 
+use std::collections::VecDeque;
 use std::ffi::OsStr;
-use std::io::Write;
-use std::os::unix::prelude::AsRawFd;
+use std::io::{Read, Write};
+use std::os::unix::prelude::{AsRawFd, FromRawFd, RawFd};
 use std::os::unix::process::CommandExt;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use std::{fs, io, thread};
 
@@ -25,10 +27,11 @@ use anyhow::Context;
 use nix::errno::Errno;
 use nix::fcntl::{FcntlArg, FdFlag};
 use nix::sys::signal::{kill, Signal};
-use nix::unistd::Pid;
+use nix::unistd::{Gid, Pid, Uid};
 use utils::pid_file::{self, PidFileRead};
 
-// These constants control the loop used to poll for process start / stop.
+// These constants control the default loop used to poll for process start / stop,
+// used when the caller doesn't supply its own `StartTimeout`.
 //
 // The loop waits for at most 10 seconds, polling every 100 ms.
 // Once a second, it prints a dot ("."), to give the user an indication that
@@ -36,11 +39,78 @@ use utils::pid_file::{self, PidFileRead};
 // it prints a notice that it's taking long, but keeps waiting.
 //
 const RETRY_UNTIL_SECS: u64 = 10;
-const RETRIES: u64 = (RETRY_UNTIL_SECS * 1000) / RETRY_INTERVAL_MILLIS;
 const RETRY_INTERVAL_MILLIS: u64 = 100;
 const DOT_EVERY_RETRIES: u64 = 10;
 const NOTICE_AFTER_RETRIES: u64 = 50;
 
+/// How many of the most recent stdout/stderr lines to keep when `capture_output` is set on
+/// [`start_process`], for attaching to the error if the process fails to start.
+const CAPTURE_TAIL_LINES: usize = 20;
+
+/// Shared buffer of the last [`CAPTURE_TAIL_LINES`] lines a spawned process printed on
+/// stdout or stderr, interleaved in the order the reader threads observed them.
+type OutputTail = Arc<Mutex<VecDeque<String>>>;
+
+/// Timeout policy for the poll loops in [`start_process`] and [`stop_process`].
+///
+/// `neon_local` can pass a generous deadline when starting/stopping a pageserver
+/// that may need to replay a lot of WAL, while tests can pass a short one so
+/// a hung process fails fast instead of eating the default 10 seconds.
+#[derive(Clone, Copy, Debug)]
+pub struct StartTimeout {
+    /// Give up and return an error once this much time has passed.
+    /// `None` means wait forever.
+    pub deadline: Option<Duration>,
+    /// How often to poll `process_status_check` / `process_has_stopped`.
+    pub poll_interval: Duration,
+}
+
+impl Default for StartTimeout {
+    fn default() -> Self {
+        StartTimeout {
+            deadline: Some(Duration::from_secs(RETRY_UNTIL_SECS)),
+            poll_interval: Duration::from_millis(RETRY_INTERVAL_MILLIS),
+        }
+    }
+}
+
+impl StartTimeout {
+    /// Number of polls to perform before giving up, or `None` to poll forever.
+    fn retries(&self) -> Option<u64> {
+        self.deadline
+            .map(|deadline| (deadline.as_millis() / self.poll_interval.as_millis().max(1)) as u64)
+    }
+}
+
+/// Returned by [`stop_process`] when the process did not stop within its [`StartTimeout`].
+#[derive(thiserror::Error, Debug)]
+#[error("{process_name} with pid {pid} did not stop in {elapsed:?}")]
+pub struct StopTimeoutError {
+    process_name: String,
+    pid: Pid,
+    elapsed: Duration,
+}
+
+/// How [`stop_process`] should escalate while waiting for the process to exit.
+#[derive(Clone, Copy, Debug)]
+pub enum StopMode {
+    /// Send SIGQUIT once, then wait (no SIGKILL escalation).
+    Immediate,
+    /// Send SIGTERM, wait up to `grace`, and if the process is still alive and
+    /// `then_kill` is set, escalate to SIGKILL and wait again.
+    Graceful { grace: Duration, then_kill: bool },
+}
+
+/// Result of a successful [`stop_process`] call: whether a signal was actually needed,
+/// and if so, which one finally reaped the process.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// The process (or its pidfile) was already gone; no signal was sent.
+    AlreadyStopped,
+    /// The process exited after being sent this signal.
+    Reaped(Signal),
+}
+
 /// Argument to `start_process`, to indicate whether it should create pidfile or if the process creates
 /// it itself.
 pub enum InitialPidFile<'t> {
@@ -50,6 +120,21 @@ pub enum InitialPidFile<'t> {
     Expect(&'t Path),
 }
 
+/// uid/gid/supplementary-groups to switch the spawned process to, so that e.g. `neon_local`
+/// can run as root (to claim the pidfile or bind a privileged resource) while the actual
+/// pageserver/safekeeper binary runs under an unprivileged service account.
+///
+/// Applied from inside a `pre_exec` closure (see [`pre_exec_drop_privileges`]), registered
+/// *after* [`pre_exec_create_pidfile`] — not via `std`'s `CommandExt::{groups,gid,uid}`
+/// builder methods, which `std` applies *before* running any `pre_exec` closures and would
+/// therefore drop privileges too early, before the pidfile is claimed.
+#[derive(Clone, Debug)]
+pub struct Credentials {
+    pub uid: u32,
+    pub gid: u32,
+    pub groups: Option<Vec<u32>>,
+}
+
 /// Start a background child process using the parameters given.
 pub fn start_process<
     F,
@@ -62,87 +147,223 @@ pub fn start_process<
     args: &[S],
     envs: EI,
     initial_pid_file: InitialPidFile,
+    start_timeout: StartTimeout,
+    credentials: Option<&Credentials>,
+    // If set, stdout/stderr are additionally teed to the console's view (via the returned
+    // error) instead of only ever landing in the log file; see `CAPTURE_TAIL_LINES`.
+    capture_output: bool,
     process_status_check: F,
 ) -> anyhow::Result<Child>
 where
     F: Fn() -> anyhow::Result<bool>,
 {
     let log_path = datadir.join(format!("{process_name}.log"));
-    let process_log_file = fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .append(true)
-        .open(&log_path)
-        .with_context(|| {
-            format!("Could not open {process_name} log file {log_path:?} for writing")
-        })?;
-    let same_file_for_stderr = process_log_file.try_clone().with_context(|| {
-        format!("Could not reuse {process_name} log file {log_path:?} for writing stderr")
-    })?;
+    let open_log_file = || -> anyhow::Result<fs::File> {
+        fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(true)
+            .open(&log_path)
+            .with_context(|| {
+                format!("Could not open {process_name} log file {log_path:?} for writing")
+            })
+    };
 
     let mut command = Command::new(command);
-    let background_command = command
-        .stdout(process_log_file)
-        .stderr(same_file_for_stderr)
-        .args(args);
+    let background_command = command.args(args);
+    let tail: Option<OutputTail> = if capture_output {
+        background_command
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        Some(Arc::new(Mutex::new(VecDeque::with_capacity(
+            CAPTURE_TAIL_LINES,
+        ))))
+    } else {
+        let stdout_log = open_log_file()?;
+        let stderr_log = stdout_log.try_clone().with_context(|| {
+            format!("Could not reuse {process_name} log file {log_path:?} for writing stderr")
+        })?;
+        background_command.stdout(stdout_log).stderr(stderr_log);
+        None
+    };
     let filled_cmd = fill_aws_secrets_vars(fill_rust_env_vars(background_command));
     filled_cmd.envs(envs);
 
+    // Isolate every spawned process into its own session/process group (pgid == pid) so
+    // stop_process can tear down a whole subtree of forked helpers at once, not just the
+    // directly-tracked pid.
+    pre_exec_isolate_process_group(filled_cmd);
+
+    // Set up the exec-error pipe before spawning: if a `pre_exec` closure below fails
+    // (e.g. the pidfile is locked by another process, or a credential switch is rejected),
+    // it writes the errno here instead of panicking blind in the child. `exec_error_write_fd`
+    // carries `FD_CLOEXEC`, so a successful exec() closes it automatically and the parent's
+    // read() below sees a clean EOF.
+    let (exec_error_read_fd, exec_error_write_fd) =
+        nix::unistd::pipe().context("create exec-error pipe")?;
+    nix::fcntl::fcntl(exec_error_write_fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+        .context("set FD_CLOEXEC on exec-error pipe")?;
+
     let pid_file_to_check = match initial_pid_file {
         InitialPidFile::Create(path) => {
-            pre_exec_create_pidfile(filled_cmd, path);
+            pre_exec_create_pidfile(filled_cmd, path, exec_error_write_fd);
             path
         }
         InitialPidFile::Expect(path) => path,
     };
 
-    let mut spawned_process = filled_cmd.spawn().with_context(|| {
+    if let Some(credentials) = credentials {
+        // Registered after pre_exec_create_pidfile above: the pidfile (possibly in a
+        // root-owned data directory) must already be claimed by the time this closure gives
+        // up our privileges.
+        pre_exec_drop_privileges(filled_cmd, credentials.clone(), exec_error_write_fd);
+    }
+
+    let spawn_result = filled_cmd.spawn().with_context(|| {
         format!("Could not spawn {process_name}, see console output and log files for details.")
-    })?;
+    });
+    // Our copy of the write end must be closed before we read, or we'd block waiting for
+    // ourselves: only the child's (possibly-inherited) copy should keep the pipe open now.
+    let _ = nix::unistd::close(exec_error_write_fd);
+    let mut spawned_process = spawn_result?;
+
+    if let Some(errno) = read_exec_error(exec_error_read_fd)? {
+        // The child failed its pre_exec setup and exited before exec(); reap it and report
+        // the exact reason immediately instead of discovering it later via a start timeout.
+        let _ = spawned_process.wait();
+        anyhow::bail!("{process_name} failed before exec: {errno}");
+    }
+
+    if let Some(tail) = &tail {
+        // One dedicated reader thread per stream: a single thread alternately reading both
+        // would deadlock if the child fills one pipe's kernel buffer while waiting for us to
+        // drain the other. Each thread tees every line to the log file and the shared tail
+        // buffer, and exits on its own once the child closes that stream (normally, at exit).
+        let stdout_pipe = spawned_process
+            .stdout
+            .take()
+            .expect("stdout was piped above");
+        let stderr_pipe = spawned_process
+            .stderr
+            .take()
+            .expect("stderr was piped above");
+        let stdout_log = open_log_file()?;
+        let stderr_log = stdout_log.try_clone().with_context(|| {
+            format!("Could not reuse {process_name} log file {log_path:?} for writing stderr")
+        })?;
+        spawn_tee_thread(stdout_pipe, stdout_log, tail.clone());
+        spawn_tee_thread(stderr_pipe, stderr_log, tail.clone());
+    }
+
     let pid = spawned_process.id();
     let pid = Pid::from_raw(
         i32::try_from(pid)
             .with_context(|| format!("Subprocess {process_name} has invalid pid {pid}"))?,
     );
 
-    for retries in 0..RETRIES {
+    let retries = start_timeout.retries();
+    for retries_done in 0.. {
+        if retries.map_or(false, |retries| retries_done >= retries) {
+            break;
+        }
         match process_started(pid, Some(pid_file_to_check), &process_status_check) {
             Ok(true) => {
                 println!("\n{process_name} started, pid: {pid}");
                 return Ok(spawned_process);
             }
             Ok(false) => {
-                if retries == NOTICE_AFTER_RETRIES {
+                if retries_done == NOTICE_AFTER_RETRIES {
                     // The process is taking a long time to start up. Keep waiting, but
                     // print a message
                     print!("\n{process_name} has not started yet, continuing to wait");
                 }
-                if retries % DOT_EVERY_RETRIES == 0 {
+                if retries_done % DOT_EVERY_RETRIES == 0 {
                     print!(".");
                     io::stdout().flush().unwrap();
                 }
-                thread::sleep(Duration::from_millis(RETRY_INTERVAL_MILLIS));
+                thread::sleep(start_timeout.poll_interval);
             }
             Err(e) => {
                 println!("{process_name} failed to start: {e:#}");
                 if let Err(e) = spawned_process.kill() {
                     println!("Could not stop {process_name} subprocess: {e:#}")
                 };
-                return Err(e);
+                return Err(attach_tail_context(e, &tail));
             }
         }
     }
     println!();
-    anyhow::bail!("{process_name} did not start in {RETRY_UNTIL_SECS} seconds");
+    let err = anyhow::anyhow!(
+        "{process_name} did not start in {:?}",
+        start_timeout.deadline.expect("loop only exits early when a deadline is set")
+    );
+    Err(attach_tail_context(err, &tail))
+}
+
+/// Append the most recently captured stdout/stderr lines (if `capture_output` was set and
+/// anything was captured) to `err`, so a fatal config error the child printed on stderr is
+/// visible right on the returned error instead of only in the log file.
+fn attach_tail_context(err: anyhow::Error, tail: &Option<OutputTail>) -> anyhow::Error {
+    let Some(tail) = tail else {
+        return err;
+    };
+    let lines = tail.lock().unwrap();
+    if lines.is_empty() {
+        return err;
+    }
+    err.context(format!(
+        "recent output:\n{}",
+        lines.iter().cloned().collect::<Vec<_>>().join("\n")
+    ))
+}
+
+/// Read raw bytes from `reader` until EOF, writing everything unconditionally to `log_writer`
+/// (so a non-UTF-8 byte can't stop log-file writes, unlike reading line-by-line as `String`
+/// would) and appending each line, lossily decoded, to `tail` (bounded to the last
+/// [`CAPTURE_TAIL_LINES`]); an unterminated last line is appended once the stream hits
+/// EOF. Runs in its own thread so a full stdout pipe can't block us from
+/// draining stderr (or vice versa).
+fn spawn_tee_thread<R: Read + Send + 'static>(mut reader: R, mut log_writer: fs::File, tail: OutputTail) {
+    thread::spawn(move || {
+        let mut buf = [0u8; 8192];
+        let mut partial_line: Vec<u8> = Vec::new();
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            };
+            let _ = log_writer.write_all(&buf[..n]);
+            partial_line.extend_from_slice(&buf[..n]);
+            while let Some(pos) = partial_line.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = partial_line.drain(..=pos).collect();
+                push_tail_line(&tail, &line);
+            }
+        }
+        if !partial_line.is_empty() {
+            push_tail_line(&tail, &partial_line);
+        }
+    });
+}
+
+/// Append one lossily-UTF8-decoded line (trailing `\n`/`\r` stripped) to `tail`.
+fn push_tail_line(tail: &OutputTail, line: &[u8]) {
+    let line = String::from_utf8_lossy(line)
+        .trim_end_matches(['\n', '\r'])
+        .to_owned();
+    let mut tail = tail.lock().unwrap();
+    if tail.len() >= CAPTURE_TAIL_LINES {
+        tail.pop_front();
+    }
+    tail.push_back(line);
 }
 
 /// Send SIGTERM to child process
 pub fn send_stop_child_process(child: &std::process::Child) -> anyhow::Result<()> {
     let pid = child.id();
-    match kill(
-        nix::unistd::Pid::from_raw(pid.try_into().unwrap()),
-        Signal::SIGTERM,
-    ) {
+    let pid = Pid::from_raw(pid.try_into().unwrap());
+    // Signal the whole process group: children started through this module always get
+    // pgid == pid via pre_exec_isolate_process_group.
+    match kill(process_group(pid), Signal::SIGTERM) {
         Ok(()) => Ok(()),
         Err(Errno::ESRCH) => {
             println!("child process with pid {pid} does not exist");
@@ -153,13 +374,18 @@ pub fn send_stop_child_process(child: &std::process::Child) -> anyhow::Result<()
 }
 
 /// Stops the process, using the pid file given. Returns Ok also if the process is already not running.
-pub fn stop_process(immediate: bool, process_name: &str, pid_file: &Path) -> anyhow::Result<()> {
+pub fn stop_process(
+    mode: StopMode,
+    process_name: &str,
+    pid_file: &Path,
+    stop_timeout: StartTimeout,
+) -> anyhow::Result<StopOutcome> {
     let pid = match pid_file::read(pid_file)
         .with_context(|| format!("read pid_file {pid_file:?}"))?
     {
         PidFileRead::NotExist => {
             println!("{process_name} is already stopped: no pid file present at {pid_file:?}");
-            return Ok(());
+            return Ok(StopOutcome::AlreadyStopped);
         }
         PidFileRead::NotHeldByAnyProcess(_) => {
             // Don't try to kill according to file contents beacuse the pid might have been re-used by another process.
@@ -168,51 +394,118 @@ pub fn stop_process(immediate: bool, process_name: &str, pid_file: &Path) -> any
             println!(
                 "No process is holding the pidfile. The process must have already exited. Leave in place to avoid race conditions: {pid_file:?}"
             );
-            return Ok(());
+            return Ok(StopOutcome::AlreadyStopped);
         }
         PidFileRead::LockedByOtherProcess(pid) => pid,
     };
     // XXX the pid could become invalid (and recycled) at any time before the kill() below.
 
-    // send signal
-    let sig = if immediate {
-        print!("Stopping {process_name} with pid {pid} immediately..");
-        Signal::SIGQUIT
-    } else {
-        print!("Stopping {process_name} with pid {pid} gracefully..");
-        Signal::SIGTERM
+    // send the initial signal
+    let initial_signal = match mode {
+        StopMode::Immediate => {
+            print!("Stopping {process_name} with pid {pid} immediately..");
+            Signal::SIGQUIT
+        }
+        StopMode::Graceful { .. } => {
+            print!("Stopping {process_name} with pid {pid} gracefully..");
+            Signal::SIGTERM
+        }
     };
     io::stdout().flush().unwrap();
-    match kill(pid, sig) {
+    // Signal the whole process group (see process_has_stopped), not just the tracked pid, so
+    // any helpers the process forked are torn down along with it.
+    match kill(process_group(pid), initial_signal) {
         Ok(()) => (),
         Err(Errno::ESRCH) => {
             // Again, don't delete the pid file. The unlink can race with a new pid file being created.
             println!(
                 "{process_name} with pid {pid} does not exist, but a pid file {pid_file:?} was found. Likely the pid got recycled. Lucky we didn't harm anyone."
             );
-            return Ok(());
+            return Ok(StopOutcome::AlreadyStopped);
         }
         Err(e) => anyhow::bail!("Failed to send signal to {process_name} with pid {pid}: {e}"),
     }
 
-    // Wait until process is gone
-    for retries in 0..RETRIES {
+    // Wait up to the grace period (for Graceful) or the full stop_timeout (for Immediate,
+    // which has no further escalation) for the process to go away.
+    let grace_timeout = match mode {
+        StopMode::Immediate => stop_timeout,
+        StopMode::Graceful { grace, .. } => StartTimeout {
+            deadline: Some(grace),
+            poll_interval: stop_timeout.poll_interval,
+        },
+    };
+    if wait_until_process_stopped(pid, process_name, grace_timeout)? {
+        return Ok(StopOutcome::Reaped(initial_signal));
+    }
+
+    if let StopMode::Graceful {
+        then_kill: true, ..
+    } = mode
+    {
+        println!(
+            "\n{process_name} with pid {pid} did not stop gracefully, escalating to SIGKILL"
+        );
+        match kill(process_group(pid), Signal::SIGKILL) {
+            Ok(()) => (),
+            Err(Errno::ESRCH) => return Ok(StopOutcome::Reaped(initial_signal)),
+            Err(e) => {
+                anyhow::bail!("Failed to send SIGKILL to {process_name} with pid {pid}: {e}")
+            }
+        }
+        if wait_until_process_stopped(pid, process_name, stop_timeout)? {
+            return Ok(StopOutcome::Reaped(Signal::SIGKILL));
+        }
+    }
+
+    // Total time actually spent waiting across the grace period and the post-SIGKILL wait.
+    let waited = match mode {
+        StopMode::Immediate => stop_timeout.deadline,
+        StopMode::Graceful {
+            grace,
+            then_kill: false,
+        } => Some(grace),
+        StopMode::Graceful {
+            grace,
+            then_kill: true,
+        } => stop_timeout.deadline.map(|deadline| grace + deadline),
+    };
+    Err(StopTimeoutError {
+        process_name: process_name.to_owned(),
+        pid,
+        elapsed: waited.unwrap_or(Duration::MAX),
+    }
+    .into())
+}
+
+/// Poll `process_has_stopped` until it reports the process is gone or `timeout` runs out.
+/// Returns `Ok(true)` if the process stopped in time, `Ok(false)` on timeout.
+fn wait_until_process_stopped(
+    pid: Pid,
+    process_name: &str,
+    timeout: StartTimeout,
+) -> anyhow::Result<bool> {
+    let retries = timeout.retries();
+    for retries_done in 0.. {
+        if retries.map_or(false, |retries| retries_done >= retries) {
+            return Ok(false);
+        }
         match process_has_stopped(pid) {
             Ok(true) => {
                 println!("\n{process_name} stopped");
-                return Ok(());
+                return Ok(true);
             }
             Ok(false) => {
-                if retries == NOTICE_AFTER_RETRIES {
+                if retries_done == NOTICE_AFTER_RETRIES {
                     // The process is taking a long time to start up. Keep waiting, but
                     // print a message
                     print!("\n{process_name} has not stopped yet, continuing to wait");
                 }
-                if retries % DOT_EVERY_RETRIES == 0 {
+                if retries_done % DOT_EVERY_RETRIES == 0 {
                     print!(".");
                     io::stdout().flush().unwrap();
                 }
-                thread::sleep(Duration::from_millis(RETRY_INTERVAL_MILLIS));
+                thread::sleep(timeout.poll_interval);
             }
             Err(e) => {
                 println!("{process_name} with pid {pid} failed to stop: {e:#}");
@@ -220,8 +513,7 @@ pub fn stop_process(immediate: bool, process_name: &str, pid_file: &Path) -> any
             }
         }
     }
-    println!();
-    anyhow::bail!("{process_name} with pid {pid} did not stop in {RETRY_UNTIL_SECS} seconds");
+    Ok(false)
 }
 
 fn fill_rust_env_vars(cmd: &mut Command) -> &mut Command {
@@ -257,11 +549,84 @@ fn fill_aws_secrets_vars(mut cmd: &mut Command) -> &mut Command {
     cmd
 }
 
+/// Put the spawned process into its own new session and process group (`setsid()` always
+/// makes the calling process both session leader and group leader, so pgid ends up equal to
+/// its own pid), and, on Linux, arm `PR_SET_PDEATHSIG` so it gets SIGTERM if this CLI process
+/// dies mid-spawn.
+///
+/// Neon storage binaries may fork their own helpers; since the CLI doesn't supervise them,
+/// a crash or a SIGKILL of just the tracked pid could otherwise leave grandchildren running
+/// against the same data directory. Because this makes pgid == pid a standing invariant for
+/// every process `start_process` spawns, `stop_process`/`process_has_stopped` can signal the
+/// group via `-pid` without needing to separately persist a pgid anywhere.
+fn pre_exec_isolate_process_group(cmd: &mut Command) -> &mut Command {
+    unsafe {
+        cmd.pre_exec(|| {
+            nix::unistd::setsid().map_err(io::Error::from)?;
+            #[cfg(target_os = "linux")]
+            {
+                // SAFETY: prctl(PR_SET_PDEATHSIG, ...) takes no pointers and is async-signal-safe.
+                if unsafe { libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGTERM) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        });
+    }
+    cmd
+}
+
+/// Magic footer appended after the errno in the exec-error pipe payload, so a short or
+/// torn read can be told apart from a genuine 4-byte errno.
+const EXEC_ERROR_MAGIC: [u8; 4] = *b"NEON";
+
+/// Write `err`'s raw OS errno (or 0 if it has none) plus [`EXEC_ERROR_MAGIC`] to the
+/// exec-error pipe. Called from inside a `pre_exec` closure, so it must stick to
+/// async-signal-safe operations: `write(2)` on an already-open fd qualifies.
+fn report_exec_error(write_fd: RawFd, err: &io::Error) {
+    let mut payload = [0u8; 8];
+    payload[0..4].copy_from_slice(&err.raw_os_error().unwrap_or(0).to_le_bytes());
+    payload[4..8].copy_from_slice(&EXEC_ERROR_MAGIC);
+    // Best-effort: if even this fails there's nothing left to do but let the child exit.
+    let _ = nix::unistd::write(write_fd, &payload);
+}
+
+/// Read the other end of the exec-error pipe set up in [`start_process`]. Returns `Ok(None)`
+/// on a clean EOF (exec succeeded, so the write end's `FD_CLOEXEC` copy closed itself),
+/// or the child's reported errno if it failed before exec.
+fn read_exec_error(read_fd: RawFd) -> anyhow::Result<Option<Errno>> {
+    // SAFETY: `read_fd` was just created by us in `start_process` and not used elsewhere.
+    let mut read_end = unsafe { fs::File::from_raw_fd(read_fd) };
+    let mut payload = [0u8; 8];
+    let mut nread = 0;
+    while nread < payload.len() {
+        let n = read_end.read(&mut payload[nread..])?;
+        if n == 0 {
+            anyhow::ensure!(nread == 0, "truncated exec-error pipe payload");
+            return Ok(None);
+        }
+        nread += n;
+    }
+    anyhow::ensure!(
+        payload[4..8] == EXEC_ERROR_MAGIC,
+        "corrupt exec-error pipe payload"
+    );
+    let errno = i32::from_le_bytes(payload[0..4].try_into().unwrap());
+    Ok(Some(Errno::from_i32(errno)))
+}
+
 /// Add a `pre_exec` to the cmd that, inbetween fork() and exec(),
 /// 1. Claims a pidfile with a fcntl lock on it and
 /// 2. Sets up the pidfile's file descriptor so that it (and the lock)
 ///    will remain held until the cmd exits.
-fn pre_exec_create_pidfile<P>(cmd: &mut Command, path: P) -> &mut Command
+///
+/// On failure, the errno is reported to the parent through `exec_error_write_fd`
+/// (see [`report_exec_error`]) instead of panicking in the child.
+fn pre_exec_create_pidfile<P>(
+    cmd: &mut Command,
+    path: P,
+    exec_error_write_fd: RawFd,
+) -> &mut Command
 where
     P: Into<PathBuf>,
 {
@@ -300,18 +665,29 @@ where
     // "async-signal-safe": https://man7.org/linux/man-pages/man7/signal-safety.7.html
     //
     // With this specific pre_exec() closure, the non-error path doesn't allocate.
-    // The error path uses `anyhow`, and hence does allocate.
-    // We take our chances there, hoping that any potential disaster is constrained
-    // to the child process (e.g., malloc has no state ourside of the child process).
-    // Last, `expect` prints to stderr, and stdio is not async-signal-safe.
-    // Again, we take our chances, making the same assumptions as for malloc.
+    // The error path used to call `expect()`, which both allocates (via its panic message)
+    // and prints to stderr; neither is async-signal-safe. We now avoid that: on error we
+    // just write the raw errno to `exec_error_write_fd` (an async-signal-safe `write(2)`)
+    // and return the error, so libc's own fork/exec machinery tears the child down without
+    // ever reaching a Rust panic.
     unsafe {
         cmd.pre_exec(move || {
-            let file = pid_file::claim_for_current_process(&path).expect("claim pid file");
+            let file = match pid_file::claim_for_current_process(&path) {
+                Ok(file) => file,
+                Err(e) => {
+                    let io_err = io::Error::new(io::ErrorKind::Other, e.to_string());
+                    report_exec_error(exec_error_write_fd, &io_err);
+                    return Err(io_err);
+                }
+            };
             // Remove the FD_CLOEXEC flag on the pidfile descriptor so that the pidfile
             // remains locked after exec.
-            nix::fcntl::fcntl(file.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::empty()))
-                .expect("remove FD_CLOEXEC");
+            if let Err(e) = nix::fcntl::fcntl(file.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::empty()))
+            {
+                let io_err = io::Error::from(e);
+                report_exec_error(exec_error_write_fd, &io_err);
+                return Err(io_err);
+            }
             // Don't run drop(file), it would close the file before we actually exec.
             std::mem::forget(file);
             Ok(())
@@ -320,6 +696,58 @@ where
     cmd
 }
 
+/// Add a `pre_exec` to the cmd that switches to `credentials`' uid/gid/supplementary groups.
+///
+/// Must be registered *after* [`pre_exec_create_pidfile`] (when both are used): `std`'s own
+/// `CommandExt::{groups,gid,uid}` builder methods are applied *before* any `pre_exec` closures
+/// run, which is too early here, since the pidfile (possibly in a root-owned data directory)
+/// needs to be claimed before we give up the privilege to do so. So instead of those builder
+/// methods, we drop privileges ourselves from inside a `pre_exec` closure, ordered after the
+/// pidfile one.
+///
+/// On failure, the errno is reported to the parent through `exec_error_write_fd`
+/// (see [`report_exec_error`]) instead of panicking in the child.
+fn pre_exec_drop_privileges(
+    cmd: &mut Command,
+    credentials: Credentials,
+    exec_error_write_fd: RawFd,
+) -> &mut Command {
+    // SAFETY: see the safety comment on pre_exec_create_pidfile; this closure sticks to
+    // async-signal-safe operations (setgroups/setgid/setuid, and report_exec_error's write(2))
+    // on both the success and error paths.
+    // The supplementary group list is converted here, before fork, so the closure below
+    // doesn't allocate.
+    let gids: Option<Vec<Gid>> = credentials
+        .groups
+        .as_ref()
+        .map(|groups| groups.iter().copied().map(Gid::from_raw).collect());
+    unsafe {
+        cmd.pre_exec(move || {
+            // Order matters: groups and gid must be set while we still have the privilege to
+            // change them, which is lost once the uid switch below succeeds.
+            if let Some(gids) = &gids {
+                if let Err(e) = nix::unistd::setgroups(gids) {
+                    let io_err = io::Error::from(e);
+                    report_exec_error(exec_error_write_fd, &io_err);
+                    return Err(io_err);
+                }
+            }
+            if let Err(e) = nix::unistd::setgid(Gid::from_raw(credentials.gid)) {
+                let io_err = io::Error::from(e);
+                report_exec_error(exec_error_write_fd, &io_err);
+                return Err(io_err);
+            }
+            if let Err(e) = nix::unistd::setuid(Uid::from_raw(credentials.uid)) {
+                let io_err = io::Error::from(e);
+                report_exec_error(exec_error_write_fd, &io_err);
+                return Err(io_err);
+            }
+            Ok(())
+        });
+    }
+    cmd
+}
+
 fn process_started<F>(
     pid: Pid,
     pid_file_to_check: Option<&Path>,
@@ -342,12 +770,82 @@ where
     }
 }
 
+/// `start_process` always isolates its child into its own process group with pgid == pid
+/// (see [`pre_exec_isolate_process_group`]), so signalling `-pid` reaches the whole group:
+/// this reports "stopped" only once every process in it, not just the original leader, is gone.
 fn process_has_stopped(pid: Pid) -> anyhow::Result<bool> {
-    match kill(pid, None) {
-        // Process exists, keep waiting
+    match kill(process_group(pid), None) {
+        // At least one process in the group still exists, keep waiting
         Ok(_) => Ok(false),
-        // Process not found, we're done
+        // Process group not found, we're done
         Err(Errno::ESRCH) => Ok(true),
-        Err(err) => anyhow::bail!("Failed to send signal to process with pid {pid}: {err}"),
+        Err(err) => anyhow::bail!("Failed to send signal to process group {pid}: {err}"),
+    }
+}
+
+/// The negative-pid form that `kill(2)` interprets as "the whole process group led by `pid`",
+/// relying on the pgid == pid invariant established by [`pre_exec_isolate_process_group`].
+fn process_group(pid: Pid) -> Pid {
+    Pid::from_raw(-pid.as_raw())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    // No tempfile crate dependency in this tree; roll our own scratch directory.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!(
+            "background_process_test_{name}_{}_{nanos}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).expect("create scratch dir");
+        dir
+    }
+
+    /// Starts a process with both `pre_exec_create_pidfile` and `pre_exec_drop_privileges`
+    /// registered and checks that the pidfile ends up held by the child.
+    /// Uses the current process' own uid/gid (dropping to "itself") so the test doesn't need
+    /// root to exercise the credential switch; since that switch changes nothing, this test
+    /// does not detect the two closures running in the opposite order.
+    #[test]
+    fn pidfile_is_claimed_before_credentials_are_dropped() {
+        let datadir = scratch_dir("pidfile_before_creds");
+        let pid_path = datadir.join("test.pid");
+
+        let credentials = Credentials {
+            uid: Uid::current().as_raw(),
+            gid: Gid::current().as_raw(),
+            groups: None,
+        };
+
+        let child = start_process(
+            "test_process",
+            &datadir,
+            Path::new("/bin/sh"),
+            &["-c", "sleep 5"],
+            std::iter::empty(),
+            InitialPidFile::Create(&pid_path),
+            StartTimeout::default(),
+            Some(&credentials),
+            false,
+            || Ok(true),
+        )
+        .expect("start_process should succeed");
+
+        match pid_file::read(&pid_path).expect("read pid file") {
+            PidFileRead::LockedByOtherProcess(pid_in_file) => {
+                assert_eq!(pid_in_file.as_raw(), child.id() as i32);
+            }
+            other => panic!("expected the pidfile to be claimed by the child, got {other:?}"),
+        }
+
+        let _ = kill(Pid::from_raw(child.id() as i32), Signal::SIGKILL);
+        let _ = fs::remove_dir_all(&datadir);
     }
 }
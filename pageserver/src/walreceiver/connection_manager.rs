@@ -9,8 +9,18 @@
 //! then a [re]connection happens, if necessary.
 //! Only WAL streaming task expects to be finished, other loops (storage broker, connection management) never exit unless cancelled explicitly via the dedicated channel.
 
-use std::{collections::HashMap, num::NonZeroU64, ops::ControlFlow, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroU64,
+    ops::ControlFlow,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use crate::metrics::{
+    WALRECEIVER_CONNECTION_ATTEMPTS_TOTAL, WALRECEIVER_CONNECTION_FAILURES_TOTAL,
+    WALRECEIVER_RECONNECT_GAP_SECONDS, WALRECEIVER_TIME_TO_FIRST_WAL_SECONDS,
+};
 use crate::task_mgr::TaskKind;
 use crate::task_mgr::WALRECEIVER_RUNTIME;
 use crate::tenant::Timeline;
@@ -18,6 +28,7 @@ use crate::{task_mgr, walreceiver::TaskStateUpdate};
 use anyhow::Context;
 use chrono::{NaiveDateTime, Utc};
 use pageserver_api::models::TimelineState;
+use rand::Rng;
 use storage_broker::proto::subscribe_safekeeper_info_request::SubscriptionKey;
 use storage_broker::proto::SafekeeperTimelineInfo;
 use storage_broker::proto::SubscribeSafekeeperInfoRequest;
@@ -39,6 +50,105 @@ use utils::{
 
 use super::{walreceiver_connection::WalConnectionStatus, TaskEvent, TaskHandle};
 
+/// Pluggable schedule for spacing out reconnect attempts to a safekeeper that just
+/// failed. `ExponentialBackoff` is the default, built from the same min/max/multiplier
+/// constants as the previous hardcoded schedule but with jitter enabled; `FixedInterval`
+/// is there for deployments (or tests) that want a
+/// predictable reconnect cadence instead of jittered exponential growth.
+#[derive(Debug, Clone, Copy)]
+pub enum ReconnectStrategy {
+    /// Always wait the same amount of time before retrying, no jitter.
+    FixedInterval(Duration),
+    ExponentialBackoff {
+        /// Backoff ceiling before the first consecutive failure.
+        initial: Duration,
+        /// Factor the backoff ceiling grows by on every consecutive failure.
+        multiplier: f64,
+        /// Upper bound the exponentially growing backoff ceiling is clamped to.
+        max: Duration,
+        /// Apply full jitter: sample the actual wait uniformly from `[0, ceiling]`
+        /// instead of waiting exactly at the ceiling, so timelines that lose the
+        /// same safekeeper simultaneously don't all reconnect in lockstep.
+        jitter: bool,
+    },
+}
+
+impl ReconnectStrategy {
+    /// Deterministic backoff ceiling for the `n`th consecutive failure (`n` starting
+    /// at 0), before jitter is applied.
+    fn delay_ceiling(&self, n: u32) -> Duration {
+        match *self {
+            ReconnectStrategy::FixedInterval(interval) => interval,
+            ReconnectStrategy::ExponentialBackoff {
+                initial,
+                multiplier,
+                max,
+                ..
+            } => {
+                // Clamp in f64 before constructing the Duration: with enough consecutive
+                // failures (e.g. max_retries left at its u32::MAX default) `n` grows large
+                // enough that the product overflows to `inf`, and `Duration::from_secs_f64`
+                // panics on non-finite input.
+                let delay_seconds = (initial.as_secs_f64() * multiplier.powi(n as i32))
+                    .min(max.as_secs_f64());
+                Duration::from_secs_f64(delay_seconds)
+            }
+        }
+    }
+
+    /// Samples the actual wait before the `n`th consecutive failure's retry.
+    fn jittered_wait(&self, n: u32) -> Duration {
+        let ceiling = self.delay_ceiling(n);
+        match *self {
+            ReconnectStrategy::FixedInterval(_) => ceiling,
+            ReconnectStrategy::ExponentialBackoff { jitter, .. } => {
+                if jitter && ceiling > Duration::ZERO {
+                    rand::thread_rng().gen_range(Duration::ZERO..=ceiling)
+                } else {
+                    ceiling
+                }
+            }
+        }
+    }
+}
+
+impl Default for ReconnectStrategy {
+    fn default() -> Self {
+        ReconnectStrategy::ExponentialBackoff {
+            initial: Duration::from_secs_f64(WALCONNECTION_RETRY_MIN_BACKOFF_SECONDS),
+            multiplier: WALCONNECTION_RETRY_BACKOFF_MULTIPLIER,
+            max: Duration::from_secs_f64(WALCONNECTION_RETRY_MAX_BACKOFF_SECONDS),
+            jitter: true,
+        }
+    }
+}
+
+/// Operator-tunable schedule for reconnecting to safekeepers after a dropped or failed
+/// connection. Previously this was a set of hardcoded consts; making it a struct lets
+/// each tenant (or the pageserver config as a whole) pick its own reconnect cadence.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionRetryOptions {
+    /// Schedule consulted to space out retries to a safekeeper that keeps failing.
+    pub reconnect_strategy: ReconnectStrategy,
+    /// Number of consecutive failures tolerated before giving up on a safekeeper.
+    pub max_retries: u32,
+    /// Bound on establishing a new connection (TCP connect + auth).
+    pub connect_timeout: Duration,
+    /// Interval at which keepalive messages are expected on an established connection.
+    pub keep_alive: Duration,
+}
+
+impl Default for ConnectionRetryOptions {
+    fn default() -> Self {
+        Self {
+            reconnect_strategy: ReconnectStrategy::default(),
+            max_retries: u32::MAX,
+            connect_timeout: Duration::from_secs(10),
+            keep_alive: Duration::from_secs(10),
+        }
+    }
+}
+
 /// Spawns the loop to take care of the timeline's WAL streaming connection.
 pub fn spawn_connection_manager_task(
     timeline: Arc<Timeline>,
@@ -46,6 +156,8 @@ pub fn spawn_connection_manager_task(
     lagging_wal_timeout: Duration,
     max_lsn_wal_lag: NonZeroU64,
     auth_token: Option<Arc<String>>,
+    connection_retry_options: ConnectionRetryOptions,
+    selection_policy: SafekeeperSelectionPolicy,
 ) {
     let mut broker_client = get_broker_client().clone();
 
@@ -67,6 +179,8 @@ pub fn spawn_connection_manager_task(
                 lagging_wal_timeout,
                 max_lsn_wal_lag,
                 auth_token,
+                connection_retry_options,
+                selection_policy,
             );
             loop {
                 select! {
@@ -125,6 +239,7 @@ async fn connection_manager_loop_step(
 
     loop {
         let time_until_next_retry = walreceiver_state.time_until_next_retry();
+        let time_until_connect_deadline = walreceiver_state.time_until_connect_deadline();
 
         // These things are happening concurrently:
         //
@@ -149,14 +264,53 @@ async fn connection_manager_loop_step(
                         match c {
                             TaskStateUpdate::Init | TaskStateUpdate::Started => {},
                             TaskStateUpdate::Progress(status) => {
+                                let was_connected = wal_connection.status.is_connected;
+                                let had_processed_wal = wal_connection.status.has_processed_wal;
+                                let had_streaming_lsn = wal_connection.status.streaming_lsn.is_some();
+
                                 if status.has_processed_wal {
                                     // We have advanced last_record_lsn by processing the WAL received
                                     // from this safekeeper. This is good enough to clean unsuccessful
                                     // retries history and allow reconnecting to this safekeeper without
                                     // sleeping for a long time.
-                                    walreceiver_state.wal_connection_retries.remove(&wal_connection.sk_id);
+                                    walreceiver_state.mark_connection_alive(wal_connection.sk_id);
                                 }
                                 wal_connection.status = status.to_owned();
+
+                                // Report per-phase failover latency: how long it took from the
+                                // moment we decided to switch safekeepers to each subsequent
+                                // milestone, broken down by the reason we switched. Measured off
+                                // the monotonic `intent_formed_at`, not the wall clock, so a
+                                // clock step mid-connection can't produce a bogus duration. These
+                                // per-reason durations are only logged, not exported as metrics.
+                                if !was_connected && wal_connection.status.is_connected {
+                                    let elapsed = wal_connection.intent_formed_at.elapsed();
+                                    info!(
+                                        "WAL connection to {:?} became connected {:?} after intent, reason: {:?}",
+                                        wal_connection.sk_id, elapsed, wal_connection.reason
+                                    );
+                                }
+                                if !had_processed_wal && wal_connection.status.has_processed_wal {
+                                    let now = Utc::now().naive_utc();
+                                    let elapsed = wal_connection.intent_formed_at.elapsed();
+                                    info!(
+                                        "WAL connection to {:?} processed first WAL {:?} after intent, reason: {:?}",
+                                        wal_connection.sk_id, elapsed, wal_connection.reason
+                                    );
+                                    walreceiver_state.connection_stats.record_first_wal(
+                                        &id,
+                                        wal_connection.sk_id,
+                                        wal_connection.intent_at,
+                                        now,
+                                    );
+                                }
+                                if !had_streaming_lsn && wal_connection.status.streaming_lsn.is_some() {
+                                    let elapsed = wal_connection.intent_formed_at.elapsed();
+                                    info!(
+                                        "WAL connection to {:?} started streaming {:?} after intent, reason: {:?}",
+                                        wal_connection.sk_id, elapsed, wal_connection.reason
+                                    );
+                                }
                             }
                         }
                     },
@@ -211,6 +365,13 @@ async fn connection_manager_loop_step(
             },
 
             _ = async { tokio::time::sleep(time_until_next_retry.unwrap()).await }, if time_until_next_retry.is_some() => {}
+
+            // A connection that accepts TCP but never actually gets to `is_connected` within
+            // `connect_timeout` is a black hole: without this, it would otherwise sit around
+            // until `lagging_wal_timeout` (which can be much longer) notices no WAL arrived.
+            _ = async { tokio::time::sleep(time_until_connect_deadline.unwrap()).await }, if time_until_connect_deadline.is_some() => {
+                walreceiver_state.handle_connect_timeout().await;
+            }
         }
 
         if let Some(new_candidate) = walreceiver_state.next_connection_candidate() {
@@ -219,6 +380,9 @@ async fn connection_manager_loop_step(
                 .change_connection(
                     new_candidate.safekeeper_id,
                     new_candidate.wal_source_connconf,
+                    new_candidate.reason,
+                    new_candidate.intent_at,
+                    new_candidate.intent_formed_at,
                 )
                 .await
         }
@@ -293,6 +457,38 @@ const WALCONNECTION_RETRY_MIN_BACKOFF_SECONDS: f64 = 0.1;
 const WALCONNECTION_RETRY_MAX_BACKOFF_SECONDS: f64 = 15.0;
 const WALCONNECTION_RETRY_BACKOFF_MULTIPLIER: f64 = 1.5;
 
+/// Operator-controllable filtering and preference applied on top of the usual
+/// `commit_lsn`-driven candidate ranking, so deployments can quarantine a misbehaving
+/// safekeeper or pin WAL streaming to same-region safekeepers without removing them
+/// from the broker.
+#[derive(Debug, Clone, Default)]
+pub struct SafekeeperSelectionPolicy {
+    /// If set, only these safekeepers are eligible candidates.
+    pub allow_list: Option<HashSet<NodeId>>,
+    /// These safekeepers are never selected, even if they have the greatest `commit_lsn`.
+    pub deny_list: HashSet<NodeId>,
+    /// Per-node preference (e.g. same-AZ) used to break ties among candidates whose
+    /// `commit_lsn` is within `max_lsn_wal_lag` of the best one. Unlisted nodes default
+    /// to weight 0.
+    pub affinity: HashMap<NodeId, i64>,
+}
+
+impl SafekeeperSelectionPolicy {
+    fn is_eligible(&self, sk_id: NodeId) -> bool {
+        if self.deny_list.contains(&sk_id) {
+            return false;
+        }
+        match &self.allow_list {
+            Some(allow_list) => allow_list.contains(&sk_id),
+            None => true,
+        }
+    }
+
+    fn affinity_weight(&self, sk_id: NodeId) -> i64 {
+        self.affinity.get(&sk_id).copied().unwrap_or(0)
+    }
+}
+
 /// All data that's needed to run endless broker loop and keep the WAL streaming connection alive, if possible.
 struct WalreceiverState {
     id: TenantTimelineId,
@@ -312,6 +508,12 @@ struct WalreceiverState {
     /// Data about all timelines, available for connection, fetched from storage broker, grouped by their corresponding safekeeper node id.
     wal_stream_candidates: HashMap<NodeId, BrokerSkTimeline>,
     auth_token: Option<Arc<String>>,
+    /// Per-tenant reconnect backoff schedule.
+    connection_retry_options: ConnectionRetryOptions,
+    /// Per-safekeeper connection attempt history, for diagnosing failover health.
+    connection_stats: ConnectionStatsCollector,
+    /// Operator-controlled allow/deny-listing and affinity weighting for candidate selection.
+    selection_policy: SafekeeperSelectionPolicy,
 }
 
 /// Current connection data.
@@ -319,6 +521,14 @@ struct WalreceiverState {
 struct WalConnection {
     /// Time when the connection was initiated.
     started_at: NaiveDateTime,
+    /// Time when the manager decided to switch to this safekeeper, before the connection
+    /// task was even spawned. Used to measure per-phase failover latency.
+    intent_at: NaiveDateTime,
+    /// Same moment as `intent_at`, on the monotonic clock, for latency math that must not
+    /// be skewed by wall-clock adjustments while the connection is live.
+    intent_formed_at: Instant,
+    /// The reason we decided to switch to this safekeeper, carried along for latency logging.
+    reason: ReconnectReason,
     /// Current safekeeper pageserver is connected to for WAL streaming.
     sk_id: NodeId,
     /// Status of the connection.
@@ -341,7 +551,172 @@ struct NewCommittedWAL {
 #[derive(Debug)]
 struct RetryInfo {
     next_retry_at: Option<NaiveDateTime>,
-    retry_duration_seconds: f64,
+    /// Number of connection attempts to this safekeeper in a row that never processed any
+    /// WAL, i.e. never got past the handshake to actually stream something useful. This is
+    /// also `n` in the reconnect strategy's `delay_ceiling(n)`, and is reset to zero as soon
+    /// as a connection makes progress.
+    consecutive_failures: u32,
+    state: SafekeeperConnectionState,
+}
+
+/// Collects per-safekeeper WAL connection attempt history, the way
+/// `select_connection_candidate`/`next_connection_candidate` drive connection attempts, and
+/// exports it as Prometheus metrics (see [`crate::metrics::WALRECEIVER_CONNECTION_ATTEMPTS_TOTAL`]
+/// and friends) so that operators can see failover health: how flaky is each safekeeper, and
+/// how long does it take after switching to it before WAL is actually flowing.
+#[derive(Debug, Default)]
+struct ConnectionStatsCollector {
+    per_node: HashMap<NodeId, NodeConnectionStats>,
+    /// The `NodeId` targeted by the most recent attempt, to know when to reset
+    /// `successive_attempts` because we switched to a different safekeeper.
+    last_attempted_node: Option<NodeId>,
+}
+
+#[derive(Debug, Default)]
+struct NodeConnectionStats {
+    /// Connect attempts to this node in a row since the last one that processed WAL.
+    successive_attempts: u32,
+    total_attempts: u64,
+    total_failures: u64,
+    /// When the last connection to this node dropped, while we're still waiting to
+    /// reconnect to it (cleared as soon as we do).
+    previous_disconnect_at: Option<NaiveDateTime>,
+    reconnect_gap: RunningMean,
+    time_to_first_wal: RunningMean,
+}
+
+/// A running mean of `Duration` samples, kept as a sum and count instead of the individual
+/// samples, so it stays O(1) in memory no matter how long a safekeeper keeps flapping over the
+/// life of a timeline.
+#[derive(Debug, Default, Clone, Copy)]
+struct RunningMean {
+    count: u64,
+    sum: Duration,
+}
+
+impl RunningMean {
+    fn record(&mut self, sample: Duration) {
+        self.count += 1;
+        self.sum += sample;
+    }
+
+    fn mean(&self) -> Option<Duration> {
+        u32::try_from(self.count)
+            .ok()
+            .filter(|&count| count > 0)
+            .map(|count| self.sum / count)
+    }
+}
+
+impl ConnectionStatsCollector {
+    /// Record that we're starting a new connection attempt to `sk_id`.
+    fn record_attempt_start(&mut self, id: &TenantTimelineId, sk_id: NodeId) {
+        let successive_attempts = if self.last_attempted_node == Some(sk_id) {
+            self.per_node.get(&sk_id).map_or(0, |s| s.successive_attempts) + 1
+        } else {
+            1
+        };
+        let stats = self.per_node.entry(sk_id).or_default();
+        stats.successive_attempts = successive_attempts;
+        stats.total_attempts += 1;
+        self.last_attempted_node = Some(sk_id);
+
+        let labels = connection_stats_labels(id, sk_id);
+        WALRECEIVER_CONNECTION_ATTEMPTS_TOTAL
+            .with_label_values(&label_refs(&labels))
+            .inc();
+    }
+
+    /// Record that `sk_id` processed its first WAL record since the connection was
+    /// (re)established at `intent_at`. Computes time-to-first-WAL and, if we'd seen this
+    /// node disconnect before, the disconnect -> reconnect gap.
+    fn record_first_wal(
+        &mut self,
+        id: &TenantTimelineId,
+        sk_id: NodeId,
+        intent_at: NaiveDateTime,
+        now: NaiveDateTime,
+    ) {
+        let stats = self.per_node.entry(sk_id).or_default();
+        let labels = connection_stats_labels(id, sk_id);
+        if let Ok(elapsed) = (now - intent_at).to_std() {
+            stats.time_to_first_wal.record(elapsed);
+            if let Some(mean) = stats.time_to_first_wal.mean() {
+                WALRECEIVER_TIME_TO_FIRST_WAL_SECONDS
+                    .with_label_values(&label_refs(&labels))
+                    .set(mean.as_secs_f64());
+            }
+        }
+        if let Some(previous_disconnect_at) = stats.previous_disconnect_at.take() {
+            if let Ok(gap) = (now - previous_disconnect_at).to_std() {
+                stats.reconnect_gap.record(gap);
+                if let Some(mean) = stats.reconnect_gap.mean() {
+                    WALRECEIVER_RECONNECT_GAP_SECONDS
+                        .with_label_values(&label_refs(&labels))
+                        .set(mean.as_secs_f64());
+                }
+            }
+        }
+        stats.successive_attempts = 0;
+    }
+
+    /// Record that the connection to `sk_id` dropped, succeeded or not.
+    fn record_disconnect(
+        &mut self,
+        id: &TenantTimelineId,
+        sk_id: NodeId,
+        succeeded: bool,
+        now: NaiveDateTime,
+    ) {
+        let stats = self.per_node.entry(sk_id).or_default();
+        if !succeeded {
+            stats.total_failures += 1;
+            let labels = connection_stats_labels(id, sk_id);
+            WALRECEIVER_CONNECTION_FAILURES_TOTAL
+                .with_label_values(&label_refs(&labels))
+                .inc();
+        }
+        stats.previous_disconnect_at = Some(now);
+    }
+}
+
+/// Label values (tenant_id, timeline_id, safekeeper_id) shared by all of the
+/// `WALRECEIVER_*` connection-stats metrics from `crate::metrics`.
+fn connection_stats_labels(id: &TenantTimelineId, sk_id: NodeId) -> [String; 3] {
+    [
+        id.tenant_id.to_string(),
+        id.timeline_id.to_string(),
+        sk_id.to_string(),
+    ]
+}
+
+/// Prometheus' `with_label_values` wants `&[&str]`; borrow out of the owned label array.
+fn label_refs(labels: &[String; 3]) -> [&str; 3] {
+    [&labels[0], &labels[1], &labels[2]]
+}
+
+/// Why a connection was dropped, used to decide how to schedule its next retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionDropOutcome {
+    /// The ordinary case: established and then lost, or superseded by a better candidate.
+    Normal,
+    /// The connection never reached `is_connected` within `connect_timeout`.
+    TimedOut,
+}
+
+/// Lifecycle of a safekeeper as a WAL streaming source, from this pageserver's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SafekeeperConnectionState {
+    /// No successful connection yet, or the node is due for a normal backoff retry.
+    NotConnected,
+    /// Last known connection to this safekeeper made progress (processed some WAL).
+    Connected,
+    /// This safekeeper failed to make progress in more than `max_retries` attempts in a row and is
+    /// excluded from candidate selection until it proves it's caught up again.
+    PermanentError {
+        /// `commit_lsn` last observed from the broker for this node at the time we gave up on it.
+        last_commit_lsn: Lsn,
+    },
 }
 
 /// Data about the timeline to connect to, received from the broker.
@@ -359,6 +734,8 @@ impl WalreceiverState {
         lagging_wal_timeout: Duration,
         max_lsn_wal_lag: NonZeroU64,
         auth_token: Option<Arc<String>>,
+        connection_retry_options: ConnectionRetryOptions,
+        selection_policy: SafekeeperSelectionPolicy,
     ) -> Self {
         let id = TenantTimelineId {
             tenant_id: timeline.tenant_id,
@@ -374,17 +751,46 @@ impl WalreceiverState {
             wal_stream_candidates: HashMap::new(),
             wal_connection_retries: HashMap::new(),
             auth_token,
+            connection_retry_options,
+            connection_stats: ConnectionStatsCollector::default(),
+            selection_policy,
         }
     }
 
-    /// Shuts down the current connection (if any) and immediately starts another one with the given connection string.
+    /// Shuts down the current connection (if any) and starts a new, WAL-ingesting one to
+    /// `new_sk_id`.
     async fn change_connection(
         &mut self,
         new_sk_id: NodeId,
         new_wal_source_connconf: PgConnectionConfig,
+        reason: ReconnectReason,
+        intent_at: NaiveDateTime,
+        intent_formed_at: Instant,
     ) {
         self.drop_old_connection(true).await;
+        self.connection_stats
+            .record_attempt_start(&self.id, new_sk_id);
+
+        self.wal_connection = Some(self.spawn_wal_connection(
+            new_sk_id,
+            new_wal_source_connconf,
+            reason,
+            intent_at,
+            intent_formed_at,
+        ));
+    }
 
+    /// Spawns a new WAL streaming task to `new_sk_id` and wraps it into a fresh
+    /// [`WalConnection`], without touching `self.wal_connection` — used to become the primary
+    /// connection.
+    fn spawn_wal_connection(
+        &self,
+        new_sk_id: NodeId,
+        new_wal_source_connconf: PgConnectionConfig,
+        reason: ReconnectReason,
+        intent_at: NaiveDateTime,
+        intent_formed_at: Instant,
+    ) -> WalConnection {
         let id = self.id;
         let connect_timeout = self.wal_connect_timeout;
         let timeline = Arc::clone(&self.timeline);
@@ -404,8 +810,11 @@ impl WalreceiverState {
         });
 
         let now = Utc::now().naive_utc();
-        self.wal_connection = Some(WalConnection {
+        WalConnection {
             started_at: now,
+            intent_at,
+            intent_formed_at,
+            reason,
             sk_id: new_sk_id,
             status: WalConnectionStatus {
                 is_connected: false,
@@ -417,12 +826,25 @@ impl WalreceiverState {
             },
             connection_task: connection_handle,
             discovered_new_wal: None,
-        });
+        }
     }
 
     /// Drops the current connection (if any) and updates retry timeout for the next
     /// connection attempt to the same safekeeper.
     async fn drop_old_connection(&mut self, needs_shutdown: bool) {
+        self.drop_old_connection_with_outcome(needs_shutdown, ConnectionDropOutcome::Normal)
+            .await
+    }
+
+    /// Like [`Self::drop_old_connection`], but lets the caller distinguish a connection that
+    /// never reached [`WalConnectionStatus::is_connected`] within `connect_timeout` from a
+    /// normal disconnect, so establishment timeouts can fast-track the backoff instead of
+    /// being blended into the regular jittered retry schedule.
+    async fn drop_old_connection_with_outcome(
+        &mut self,
+        needs_shutdown: bool,
+        outcome: ConnectionDropOutcome,
+    ) {
         let wal_connection = match self.wal_connection.take() {
             Some(wal_connection) => wal_connection,
             None => return,
@@ -432,25 +854,70 @@ impl WalreceiverState {
             wal_connection.connection_task.shutdown().await;
         }
 
+        self.connection_stats.record_disconnect(
+            &self.id,
+            wal_connection.sk_id,
+            wal_connection.status.has_processed_wal,
+            Utc::now().naive_utc(),
+        );
+
         let retry = self
             .wal_connection_retries
             .entry(wal_connection.sk_id)
             .or_insert(RetryInfo {
                 next_retry_at: None,
-                retry_duration_seconds: WALCONNECTION_RETRY_MIN_BACKOFF_SECONDS,
+                consecutive_failures: 0,
+                state: SafekeeperConnectionState::NotConnected,
             });
 
+        if wal_connection.status.has_processed_wal {
+            // The connection served at least some WAL before going away, so the safekeeper
+            // is known-good: forgive its failure history. A connection that served WAL can
+            // never also be a TimedOut establishment, so this can't shadow that case.
+            retry.consecutive_failures = 0;
+            retry.state = SafekeeperConnectionState::Connected;
+        } else {
+            retry.consecutive_failures += 1;
+            if retry.consecutive_failures > self.connection_retry_options.max_retries {
+                let last_commit_lsn = self
+                    .wal_stream_candidates
+                    .get(&wal_connection.sk_id)
+                    .map(|sk| Lsn(sk.timeline.commit_lsn))
+                    .unwrap_or(Lsn::INVALID);
+                if retry.state != (SafekeeperConnectionState::PermanentError { last_commit_lsn }) {
+                    warn!(
+                        "safekeeper {:?} failed to make progress in {} consecutive attempts, marking as PermanentError",
+                        wal_connection.sk_id, retry.consecutive_failures
+                    );
+                }
+                retry.state = SafekeeperConnectionState::PermanentError { last_commit_lsn };
+            }
+        }
+
         let now = Utc::now().naive_utc();
 
-        // Schedule the next retry attempt. We want to have exponential backoff for connection attempts,
-        // and we add backoff to the time when we started the connection attempt. If the connection
-        // was active for a long time, then next_retry_at will be in the past.
-        retry.next_retry_at =
-            wal_connection
-                .started_at
-                .checked_add_signed(chrono::Duration::milliseconds(
-                    (retry.retry_duration_seconds * 1000.0) as i64,
-                ));
+        // The reconnect strategy's ceiling for this safekeeper keeps growing (exponentially,
+        // by default) with `retry.consecutive_failures`. Scheduling the retry exactly at that
+        // ceiling makes thousands of timelines that lost the same safekeeper reconnect in
+        // lockstep, so the strategy jitters the actual wait, freshly on every attempt.
+        //
+        // Exception: a connection that never got past establishment (TimedOut) is a
+        // black-hole safekeeper, not ordinary jitter-worthy bad luck. Skip the jitter and
+        // schedule the retry at the backoff ceiling itself, the top of the range the jitter
+        // would otherwise sample from.
+        let reconnect_strategy = self.connection_retry_options.reconnect_strategy;
+        let scheduled_wait = match outcome {
+            ConnectionDropOutcome::Normal => {
+                reconnect_strategy.jittered_wait(retry.consecutive_failures)
+            }
+            ConnectionDropOutcome::TimedOut => {
+                reconnect_strategy.delay_ceiling(retry.consecutive_failures)
+            }
+        };
+
+        retry.next_retry_at = wal_connection
+            .started_at
+            .checked_add_signed(chrono::Duration::from_std(scheduled_wait).unwrap_or_default());
 
         if let Some(next) = &retry.next_retry_at {
             if next > &now {
@@ -460,15 +927,23 @@ impl WalreceiverState {
                 );
             }
         }
+    }
 
-        let next_retry_duration =
-            retry.retry_duration_seconds * WALCONNECTION_RETRY_BACKOFF_MULTIPLIER;
-        // Clamp the next retry duration to the maximum allowed.
-        let next_retry_duration = next_retry_duration.min(WALCONNECTION_RETRY_MAX_BACKOFF_SECONDS);
-        // Clamp the next retry duration to the minimum allowed.
-        let next_retry_duration = next_retry_duration.max(WALCONNECTION_RETRY_MIN_BACKOFF_SECONDS);
-
-        retry.retry_duration_seconds = next_retry_duration;
+    /// Resets a safekeeper's failure history after a connection to it made progress,
+    /// without forgetting that we ever talked to it (unlike the old blanket `remove`),
+    /// so its [`SafekeeperConnectionState`] stays visible for diagnosability.
+    fn mark_connection_alive(&mut self, sk_id: NodeId) {
+        let retry = self
+            .wal_connection_retries
+            .entry(sk_id)
+            .or_insert(RetryInfo {
+                next_retry_at: None,
+                consecutive_failures: 0,
+                state: SafekeeperConnectionState::NotConnected,
+            });
+        retry.next_retry_at = None;
+        retry.consecutive_failures = 0;
+        retry.state = SafekeeperConnectionState::Connected;
     }
 
     /// Returns time needed to wait to have a new candidate for WAL streaming.
@@ -485,10 +960,65 @@ impl WalreceiverState {
         next_retry_at.and_then(|next_retry_at| (next_retry_at - now).to_std().ok())
     }
 
+    /// Time left until the current connection's establishment deadline, if it hasn't
+    /// connected yet. `None` if there's no connection, or it's already connected (in which
+    /// case `wal_connect_timeout`/keepalive staleness, not this deadline, governs it).
+    fn time_until_connect_deadline(&self) -> Option<Duration> {
+        let wal_connection = self.wal_connection.as_ref()?;
+        if wal_connection.status.is_connected {
+            return None;
+        }
+
+        let now = Utc::now().naive_utc();
+        let deadline = wal_connection.started_at.checked_add_signed(
+            chrono::Duration::from_std(self.connection_retry_options.connect_timeout).ok()?,
+        )?;
+
+        (deadline - now).to_std().ok().or(Some(Duration::ZERO))
+    }
+
+    /// Aborts a connection that has been establishing for longer than `connect_timeout`
+    /// without ever reporting `is_connected`, and fast-tracks its backoff rather than
+    /// waiting for `wal_connect_timeout`/`lagging_wal_timeout` to notice.
+    async fn handle_connect_timeout(&mut self) {
+        let Some(wal_connection) = self.wal_connection.as_ref() else {
+            return;
+        };
+        if wal_connection.status.is_connected {
+            return;
+        }
+
+        warn!(
+            "WAL connection to {:?} timed out before establishing, reason: {:?}",
+            wal_connection.sk_id, wal_connection.reason
+        );
+        self.drop_old_connection_with_outcome(true, ConnectionDropOutcome::TimedOut)
+            .await;
+    }
+
     /// Adds another broker timeline into the state, if its more recent than the one already added there for the same key.
     fn register_timeline_update(&mut self, timeline_update: SafekeeperTimelineInfo) {
+        let sk_id = NodeId(timeline_update.safekeeper_id);
+        let new_commit_lsn = Lsn(timeline_update.commit_lsn);
+
+        // A safekeeper only leaves PermanentError once it proves it's alive again: the
+        // broker reports a commit_lsn past the one we last saw when we gave up on it.
+        if let Some(retry) = self.wal_connection_retries.get_mut(&sk_id) {
+            if let SafekeeperConnectionState::PermanentError { last_commit_lsn } = retry.state {
+                if new_commit_lsn > last_commit_lsn {
+                    info!(
+                        "safekeeper {:?} advanced commit_lsn from {} to {} while in PermanentError, re-admitting it as a candidate",
+                        sk_id, last_commit_lsn, new_commit_lsn
+                    );
+                    retry.state = SafekeeperConnectionState::NotConnected;
+                    retry.consecutive_failures = 0;
+                    retry.next_retry_at = None;
+                }
+            }
+        }
+
         self.wal_stream_candidates.insert(
-            NodeId(timeline_update.safekeeper_id),
+            sk_id,
             BrokerSkTimeline {
                 timeline: timeline_update,
                 latest_update: Utc::now().naive_utc(),
@@ -527,7 +1057,8 @@ impl WalreceiverState {
                     (now - existing_wal_connection.status.latest_connection_update).to_std()
                 {
                     // Drop connection if we haven't received keepalive message for a while.
-                    if latest_interaciton > self.wal_connect_timeout {
+                    let keep_alive_threshold = self.connection_retry_options.keep_alive;
+                    if latest_interaciton > keep_alive_threshold {
                         return Some(NewWalConnectionCandidate {
                             safekeeper_id: new_sk_id,
                             wal_source_connconf: new_wal_source_connconf,
@@ -536,8 +1067,29 @@ impl WalreceiverState {
                                     existing_wal_connection.status.latest_connection_update,
                                 ),
                                 check_time: now,
-                                threshold: self.wal_connect_timeout,
+                                threshold: keep_alive_threshold,
+                            },
+                            intent_at: now,
+                            intent_formed_at: Instant::now(),
+                        });
+                    }
+                }
+
+                if existing_wal_connection.status.streaming_lsn.is_none() {
+                    let stalled_for = existing_wal_connection.intent_formed_at.elapsed();
+                    if stalled_for > self.wal_connect_timeout {
+                        // Connected (or still connecting) for a while, but never made it to
+                        // streaming WAL: force a recycle onto whatever candidate is available,
+                        // even if it has less WAL than the stalled safekeeper.
+                        return Some(NewWalConnectionCandidate {
+                            safekeeper_id: new_sk_id,
+                            wal_source_connconf: new_wal_source_connconf,
+                            reason: ReconnectReason::StalledConnecting {
+                                intent_formed_at: existing_wal_connection.intent_formed_at,
+                                timeout: self.wal_connect_timeout,
                             },
+                            intent_at: now,
+                            intent_formed_at: Instant::now(),
                         });
                     }
                 }
@@ -561,6 +1113,8 @@ impl WalreceiverState {
                                         new_commit_lsn,
                                         threshold: self.max_lsn_wal_lag,
                                     },
+                                    intent_at: now,
+                                    intent_formed_at: Instant::now(),
                                 });
                             }
                         }
@@ -640,6 +1194,8 @@ impl WalreceiverState {
                                     check_time: now,
                                     threshold: self.lagging_wal_timeout,
                                 },
+                                intent_at: now,
+                                intent_formed_at: Instant::now(),
                             });
                         }
                     }
@@ -654,6 +1210,8 @@ impl WalreceiverState {
                     safekeeper_id: new_sk_id,
                     wal_source_connconf: new_wal_source_connconf,
                     reason: ReconnectReason::NoExistingConnection,
+                    intent_at: Utc::now().naive_utc(),
+                    intent_formed_at: Instant::now(),
                 });
             }
         }
@@ -667,17 +1225,35 @@ impl WalreceiverState {
     /// The candidate that is chosen:
     /// * has no pending retry cooldown
     /// * has greatest commit_lsn among the ones that are left
+    /// Picks the best candidate by `commit_lsn`, except among candidates whose `commit_lsn`
+    /// is within `max_lsn_wal_lag` of the best one, where the one with the highest
+    /// [`SafekeeperSelectionPolicy::affinity_weight`] wins instead (e.g. to prefer a same-AZ
+    /// safekeeper over a marginally more caught-up one in a different region).
     fn select_connection_candidate(
         &self,
         node_to_omit: Option<NodeId>,
     ) -> Option<(NodeId, &SafekeeperTimelineInfo, PgConnectionConfig)> {
-        self.applicable_connection_candidates()
+        let mut candidates: Vec<_> = self
+            .applicable_connection_candidates()
             .filter(|&(sk_id, _, _)| Some(sk_id) != node_to_omit)
-            .max_by_key(|(_, info, _)| info.commit_lsn)
+            .collect();
+
+        let best_commit_lsn = candidates
+            .iter()
+            .map(|(_, info, _)| info.commit_lsn)
+            .max()?;
+        candidates.retain(|(_, info, _)| {
+            best_commit_lsn.saturating_sub(info.commit_lsn) <= self.max_lsn_wal_lag.get()
+        });
+
+        candidates.into_iter().max_by_key(|&(sk_id, info, _)| {
+            (self.selection_policy.affinity_weight(sk_id), info.commit_lsn)
+        })
     }
 
     /// Returns a list of safekeepers that have valid info and ready for connection.
-    /// Some safekeepers are filtered by the retry cooldown.
+    /// Some safekeepers are filtered by the retry cooldown, and by the operator-controlled
+    /// [`SafekeeperSelectionPolicy`] allow/deny-list.
     fn applicable_connection_candidates(
         &self,
     ) -> impl Iterator<Item = (NodeId, &SafekeeperTimelineInfo, PgConnectionConfig)> {
@@ -686,6 +1262,13 @@ impl WalreceiverState {
         self.wal_stream_candidates
             .iter()
             .filter(|(_, info)| Lsn(info.timeline.commit_lsn) != Lsn::INVALID)
+            .filter(move |(sk_id, _)| self.selection_policy.is_eligible(*sk_id))
+            .filter(move |(sk_id, _)| {
+                !matches!(
+                    self.wal_connection_retries.get(sk_id).map(|r| r.state),
+                    Some(SafekeeperConnectionState::PermanentError { .. })
+                )
+            })
             .filter(move |(sk_id, _)| {
                 let next_retry_at = self
                     .wal_connection_retries
@@ -751,13 +1334,17 @@ impl WalreceiverState {
 struct NewWalConnectionCandidate {
     safekeeper_id: NodeId,
     wal_source_connconf: PgConnectionConfig,
-    // This field is used in `derive(Debug)` only.
-    #[allow(dead_code)]
     reason: ReconnectReason,
+    /// Moment the manager decided to switch to this candidate, used to measure how long
+    /// the subsequent connect/stream phases take.
+    intent_at: NaiveDateTime,
+    /// Same moment as `intent_at`, but on the monotonic clock, so the per-phase latency
+    /// measured off it can't be skewed by a wall-clock adjustment mid-connection.
+    intent_formed_at: Instant,
 }
 
 /// Stores the reason why WAL connection was switched, for furter debugging purposes.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ReconnectReason {
     NoExistingConnection,
     LaggingWal {
@@ -778,6 +1365,13 @@ enum ReconnectReason {
         check_time: NaiveDateTime,
         threshold: Duration,
     },
+    /// Connection has been alive for longer than `timeout` since its intent was formed,
+    /// but has never reported a `streaming_lsn`: it is stuck somewhere between connecting
+    /// and actually streaming WAL, so we force a recycle rather than waiting on it forever.
+    StalledConnecting {
+        intent_formed_at: Instant,
+        timeout: Duration,
+    },
 }
 
 fn wal_stream_connection_config(
@@ -882,6 +1476,9 @@ mod tests {
         state.max_lsn_wal_lag = NonZeroU64::new(100).unwrap();
         state.wal_connection = Some(WalConnection {
             started_at: now,
+            intent_at: now,
+            intent_formed_at: Instant::now(),
+            reason: ReconnectReason::NoExistingConnection,
             sk_id: connected_sk_id,
             status: connection_status.clone(),
             connection_task: TaskHandle::spawn(move |sender, _| async move {
@@ -1007,7 +1604,8 @@ mod tests {
             NodeId(0),
             RetryInfo {
                 next_retry_at: now.checked_add_signed(chrono::Duration::hours(1)),
-                retry_duration_seconds: WALCONNECTION_RETRY_MAX_BACKOFF_SECONDS,
+                consecutive_failures: 10,
+                state: SafekeeperConnectionState::NotConnected,
             },
         )]);
 
@@ -1044,6 +1642,9 @@ mod tests {
 
         state.wal_connection = Some(WalConnection {
             started_at: now,
+            intent_at: now,
+            intent_formed_at: Instant::now(),
+            reason: ReconnectReason::NoExistingConnection,
             sk_id: connected_sk_id,
             status: connection_status.clone(),
             connection_task: TaskHandle::spawn(move |sender, _| async move {
@@ -1094,9 +1695,10 @@ mod tests {
         let current_lsn = Lsn(100_000).align();
         let now = Utc::now().naive_utc();
 
-        let wal_connect_timeout = chrono::Duration::from_std(state.wal_connect_timeout)?;
+        let keep_alive_threshold =
+            chrono::Duration::from_std(state.connection_retry_options.keep_alive)?;
         let time_over_threshold =
-            Utc::now().naive_utc() - wal_connect_timeout - wal_connect_timeout;
+            Utc::now().naive_utc() - keep_alive_threshold - keep_alive_threshold;
 
         let connection_status = WalConnectionStatus {
             is_connected: true,
@@ -1109,6 +1711,9 @@ mod tests {
 
         state.wal_connection = Some(WalConnection {
             started_at: now,
+            intent_at: now,
+            intent_formed_at: Instant::now(),
+            reason: ReconnectReason::NoExistingConnection,
             sk_id: NodeId(1),
             status: connection_status.clone(),
             connection_task: TaskHandle::spawn(move |sender, _| async move {
@@ -1136,7 +1741,7 @@ mod tests {
                 ..
             } => {
                 assert_eq!(last_keep_alive, Some(time_over_threshold));
-                assert_eq!(threshold, state.lagging_wal_timeout);
+                assert_eq!(threshold, state.connection_retry_options.keep_alive);
             }
             unexpected => panic!("Unexpected reason: {unexpected:?}"),
         }
@@ -1171,6 +1776,9 @@ mod tests {
 
         state.wal_connection = Some(WalConnection {
             started_at: now,
+            intent_at: now,
+            intent_formed_at: Instant::now(),
+            reason: ReconnectReason::NoExistingConnection,
             sk_id: NodeId(1),
             status: connection_status,
             connection_task: TaskHandle::spawn(move |_, _| async move { Ok(()) }),
@@ -1236,6 +1844,9 @@ mod tests {
             wal_stream_candidates: HashMap::new(),
             wal_connection_retries: HashMap::new(),
             auth_token: None,
+            connection_retry_options: ConnectionRetryOptions::default(),
+            connection_stats: ConnectionStatsCollector::default(),
+            selection_policy: SafekeeperSelectionPolicy::default(),
         }
     }
 }